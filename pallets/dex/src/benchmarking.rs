@@ -0,0 +1,114 @@
+//! Benchmarking setup for pallet-dex
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Dex;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::traits::{fungibles, Currency};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+
+fn native<T: Config>(units: u32) -> BalanceOf<T> {
+	T::Currency::minimum_balance().max(1u32.into()) * units.into()
+}
+
+// Create an asset without a pool and fund `who` with both currencies. The asset is created as
+// non-sufficient, like assets created through `Assets::create`, which is the more expensive case.
+fn setup_asset<T: Config>(who: &T::AccountId) -> AssetIdOf<T>
+where
+	T::Assets: fungibles::Create<T::AccountId> + fungibles::Mutate<T::AccountId>,
+	AssetIdOf<T>: From<u32>,
+{
+	let asset_id: AssetIdOf<T> = 100u32.into();
+	<T::Assets as fungibles::Create<_>>::create(asset_id, who.clone(), false, 1u32.into()).unwrap();
+	fund::<T>(who, asset_id);
+	asset_id
+}
+
+fn fund<T: Config>(who: &T::AccountId, asset_id: AssetIdOf<T>)
+where
+	T::Assets: fungibles::Mutate<T::AccountId>,
+{
+	T::Currency::make_free_balance_be(who, native::<T>(1_000_000));
+	<T::Assets as fungibles::Mutate<_>>::mint_into(asset_id, who, native::<T>(1_000_000)).unwrap();
+}
+
+benchmarks! {
+	where_clause {
+		where
+			T::Assets: fungibles::Create<T::AccountId> + fungibles::Mutate<T::AccountId>,
+			AssetIdOf<T>: From<u32>,
+	}
+
+	add_liquidity {
+		// Joining an existing pool also computes the deposit ratio and shares.
+		let provider: T::AccountId = account("provider", 0, SEED);
+		let asset_id = setup_asset::<T>(&provider);
+		Dex::<T>::add_liquidity(
+			RawOrigin::Signed(provider).into(),
+			asset_id,
+			native::<T>(1_000),
+			native::<T>(4_000),
+			0u32.into(),
+		)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, asset_id);
+	}: _(RawOrigin::Signed(caller.clone()), asset_id, native::<T>(500), native::<T>(4_000), 0u32.into())
+	verify {
+		assert_eq!(Shares::<T>::get(asset_id, &caller), native::<T>(500));
+	}
+
+	remove_liquidity {
+		// Removing the last shares closes the pool and reaps the pool account.
+		let caller: T::AccountId = whitelisted_caller();
+		let asset_id = setup_asset::<T>(&caller);
+		Dex::<T>::add_liquidity(
+			RawOrigin::Signed(caller.clone()).into(),
+			asset_id,
+			native::<T>(1_000),
+			native::<T>(4_000),
+			0u32.into(),
+		)?;
+	}: _(RawOrigin::Signed(caller.clone()), asset_id, native::<T>(1_000), 0u32.into(), 0u32.into())
+	verify {
+		assert!(Pools::<T>::get(asset_id).is_none());
+	}
+
+	swap_native_for_asset {
+		let provider: T::AccountId = account("provider", 0, SEED);
+		let asset_id = setup_asset::<T>(&provider);
+		Dex::<T>::add_liquidity(
+			RawOrigin::Signed(provider).into(),
+			asset_id,
+			native::<T>(1_000),
+			native::<T>(4_000),
+			0u32.into(),
+		)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, asset_id);
+	}: _(RawOrigin::Signed(caller), asset_id, native::<T>(100), 0u32.into())
+	verify {
+		assert!(Pools::<T>::get(asset_id).unwrap().native_reserve > native::<T>(1_000));
+	}
+
+	swap_asset_for_native {
+		let provider: T::AccountId = account("provider", 0, SEED);
+		let asset_id = setup_asset::<T>(&provider);
+		Dex::<T>::add_liquidity(
+			RawOrigin::Signed(provider).into(),
+			asset_id,
+			native::<T>(1_000),
+			native::<T>(4_000),
+			0u32.into(),
+		)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, asset_id);
+	}: _(RawOrigin::Signed(caller), asset_id, native::<T>(400), 0u32.into())
+	verify {
+		assert!(Pools::<T>::get(asset_id).unwrap().asset_reserve > native::<T>(4_000));
+	}
+
+	impl_benchmark_test_suite!(Dex, crate::mock::new_test_ext(), crate::mock::Test);
+}
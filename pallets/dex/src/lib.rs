@@ -0,0 +1,558 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A minimal constant-product AMM pairing the native currency with fungible assets.
+///
+/// Every asset can have one pool against the native currency. Liquidity providers receive shares
+/// tracked by this pallet, and each swap leaves a `SwapFee` cut of its input in the pool. Other
+/// pallets can derive a time-weighted average price from the cumulative prices kept in
+/// `PriceAccumulators`. The `quote_*` helpers only return the spot price, which can be moved and
+/// restored within a single block, and must not be used as an oracle.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+pub use weights::WeightInfo;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{fungibles, Currency, ExistenceRequirement},
+		transactional, PalletId,
+	};
+	use frame_system::pallet_prelude::*;
+
+	use crate::WeightInfo;
+	use sp_core::U256;
+	use sp_runtime::{
+		traits::{AccountIdConversion, CheckedAdd, SaturatedConversion, Saturating, Zero},
+		FixedPointNumber, FixedU128, Permill,
+	};
+
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	pub type AssetIdOf<T> = <<T as Config>::Assets as fungibles::Inspect<
+		<T as frame_system::Config>::AccountId,
+	>>::AssetId;
+
+	/// Reserves of a pool and the liquidity shares issued against them.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, Default)]
+	pub struct Pool<Balance> {
+		/// Native currency held by the pool.
+		pub native_reserve: Balance,
+		/// Asset held by the pool.
+		pub asset_reserve: Balance,
+		/// Total liquidity shares owned by providers.
+		pub total_shares: Balance,
+	}
+
+	/// Running sums of the prices of a pool, for computing time-weighted averages.
+	///
+	/// Each sum grows by the pool's price times the number of blocks that price was in effect. It
+	/// is updated before the first change to the reserves in a block, so it only ever sees prices
+	/// from the end of earlier blocks. The average price between two observations is the
+	/// difference of the sums divided by the number of blocks between them. The sums wrap around
+	/// on overflow, so take their difference with wrapping subtraction on the inner value. The two
+	/// sums are updated independently, and a price too large for `FixedU128` adds nothing to its
+	/// own sum for that interval.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, Default)]
+	pub struct PriceAccumulator<BlockNumber> {
+		/// Sum of the price of one unit of the asset in native currency.
+		pub asset_price_cumulative: FixedU128,
+		/// Sum of the price of one unit of native currency in the asset.
+		pub native_price_cumulative: FixedU128,
+		/// Block up to which the sums are accumulated.
+		pub last_update: BlockNumber,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The native currency every pool is paired against.
+		type Currency: Currency<Self::AccountId>;
+
+		/// The fungible assets that can be pooled, e.g. `pallet_assets`.
+		type Assets: fungibles::Transfer<Self::AccountId, Balance = BalanceOf<Self>>;
+
+		/// Identifier from which the account of each pool is derived.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// Share of every swap input kept by the pool as a reward for liquidity providers.
+		#[pallet::constant]
+		type SwapFee: Get<Permill>;
+
+		/// Weight information for the extrinsics of this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The pool of each asset that has one.
+	#[pallet::storage]
+	#[pallet::getter(fn pools)]
+	pub type Pools<T: Config> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Pool<BalanceOf<T>>>;
+
+	/// Liquidity shares held by each provider of a pool.
+	#[pallet::storage]
+	#[pallet::getter(fn shares)]
+	pub type Shares<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		Blake2_128Concat,
+		T::AccountId,
+		BalanceOf<T>,
+		ValueQuery,
+	>;
+
+	/// Cumulative prices of each open pool.
+	#[pallet::storage]
+	#[pallet::getter(fn price_accumulators)]
+	pub type PriceAccumulators<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, PriceAccumulator<T::BlockNumber>>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A pool was created by its first liquidity provider. [who, asset_id]
+		PoolCreated(T::AccountId, AssetIdOf<T>),
+		/// Liquidity was added to a pool. [who, asset_id, native_amount, asset_amount, shares]
+		LiquidityAdded(T::AccountId, AssetIdOf<T>, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+		/// Liquidity was removed from a pool. [who, asset_id, native_amount, asset_amount, shares]
+		LiquidityRemoved(T::AccountId, AssetIdOf<T>, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+		/// The last liquidity was removed and the pool was closed. [asset_id]
+		PoolClosed(AssetIdOf<T>),
+		/// Native currency was swapped for an asset. [who, asset_id, native_in, asset_out]
+		SwappedNativeForAsset(T::AccountId, AssetIdOf<T>, BalanceOf<T>, BalanceOf<T>),
+		/// An asset was swapped for native currency. [who, asset_id, asset_in, native_out]
+		SwappedAssetForNative(T::AccountId, AssetIdOf<T>, BalanceOf<T>, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// There is no pool for this asset.
+		PoolNotFound,
+		/// An amount going into or out of the pool is zero.
+		ZeroAmount,
+		/// The pool has too little liquidity for this operation.
+		InsufficientLiquidity,
+		/// The caller does not hold that many liquidity shares.
+		InsufficientShares,
+		/// The operation would be executed outside the limits given by the caller.
+		SlippageExceeded,
+		/// An arithmetic operation overflowed.
+		Overflow,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Deposit native currency and an asset into the asset's pool in exchange for shares.
+		///
+		/// The first provider creates the pool and sets its price with the ratio of the two
+		/// amounts. Later providers deposit `native_amount` and as much of the asset as the
+		/// current ratio requires, which must not exceed `max_asset_amount`.
+		#[pallet::weight(T::WeightInfo::add_liquidity())]
+		#[transactional]
+		pub fn add_liquidity(
+			origin: OriginFor<T>,
+			asset_id: AssetIdOf<T>,
+			native_amount: BalanceOf<T>,
+			max_asset_amount: BalanceOf<T>,
+			min_shares: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				!native_amount.is_zero() && !max_asset_amount.is_zero(),
+				Error::<T>::ZeroAmount
+			);
+
+			let mut pool = Self::pools(asset_id).unwrap_or_default();
+			Self::update_price_accumulator(asset_id, &pool);
+			let created = pool.total_shares.is_zero();
+			let (asset_amount, shares) = if created {
+				// Shares of a new pool start out equal to the native deposit.
+				(max_asset_amount, native_amount)
+			} else {
+				// Round the asset deposit up so that adding liquidity never dilutes the pool.
+				let asset_amount =
+					Self::mul_div_ceil(native_amount, pool.asset_reserve, pool.native_reserve)?;
+				ensure!(asset_amount <= max_asset_amount, Error::<T>::SlippageExceeded);
+				let shares = Self::mul_div(native_amount, pool.total_shares, pool.native_reserve)?;
+				(asset_amount, shares)
+			};
+			ensure!(!shares.is_zero(), Error::<T>::InsufficientLiquidity);
+			ensure!(shares >= min_shares, Error::<T>::SlippageExceeded);
+
+			// The native transfer comes first so that the pool account exists before it is
+			// credited with the asset.
+			let pool_account = Self::pool_account(asset_id);
+			T::Currency::transfer(
+				&who,
+				&pool_account,
+				native_amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			<T::Assets as fungibles::Transfer<_>>::transfer(
+				asset_id,
+				&who,
+				&pool_account,
+				asset_amount,
+				true,
+			)?;
+
+			pool.native_reserve =
+				pool.native_reserve.checked_add(&native_amount).ok_or(Error::<T>::Overflow)?;
+			pool.asset_reserve =
+				pool.asset_reserve.checked_add(&asset_amount).ok_or(Error::<T>::Overflow)?;
+			pool.total_shares =
+				pool.total_shares.checked_add(&shares).ok_or(Error::<T>::Overflow)?;
+			<Pools<T>>::insert(asset_id, pool);
+			<Shares<T>>::mutate(asset_id, &who, |held| *held = held.saturating_add(shares));
+
+			if created {
+				Self::deposit_event(Event::PoolCreated(who.clone(), asset_id));
+			}
+			Self::deposit_event(Event::LiquidityAdded(
+				who,
+				asset_id,
+				native_amount,
+				asset_amount,
+				shares,
+			));
+			Ok(())
+		}
+
+		/// Burn liquidity shares in exchange for the matching part of both pool reserves.
+		///
+		/// Removing the last shares of a pool pays out everything its account holds and closes it.
+		#[pallet::weight(T::WeightInfo::remove_liquidity())]
+		#[transactional]
+		pub fn remove_liquidity(
+			origin: OriginFor<T>,
+			asset_id: AssetIdOf<T>,
+			shares: BalanceOf<T>,
+			min_native_amount: BalanceOf<T>,
+			min_asset_amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!shares.is_zero(), Error::<T>::ZeroAmount);
+
+			let mut pool = Self::pools(asset_id).ok_or(Error::<T>::PoolNotFound)?;
+			Self::update_price_accumulator(asset_id, &pool);
+			let held = Self::shares(asset_id, &who);
+			ensure!(held >= shares, Error::<T>::InsufficientShares);
+
+			let native_amount = Self::mul_div(shares, pool.native_reserve, pool.total_shares)?;
+			let asset_amount = Self::mul_div(shares, pool.asset_reserve, pool.total_shares)?;
+			ensure!(
+				native_amount >= min_native_amount && asset_amount >= min_asset_amount,
+				Error::<T>::SlippageExceeded
+			);
+
+			pool.native_reserve = pool.native_reserve.saturating_sub(native_amount);
+			pool.asset_reserve = pool.asset_reserve.saturating_sub(asset_amount);
+			pool.total_shares = pool.total_shares.saturating_sub(shares);
+			let closed = pool.total_shares.is_zero();
+
+			// Closing pays out everything the pool account holds, including funds sent to it
+			// directly: a leftover asset balance would keep the account alive and make the final
+			// native transfer fail. The pool account is only allowed to die once the pool is
+			// closed, and the asset goes out first so that the account holds no asset when its
+			// native balance is removed.
+			let pool_account = Self::pool_account(asset_id);
+			let asset_amount = if closed {
+				<T::Assets as fungibles::Inspect<_>>::balance(asset_id, &pool_account)
+			} else {
+				asset_amount
+			};
+			<T::Assets as fungibles::Transfer<_>>::transfer(
+				asset_id,
+				&pool_account,
+				&who,
+				asset_amount,
+				!closed,
+			)?;
+			// Other assets sent to the pool account hold consumer references that keep it from
+			// being reaped. The existential deposit then stays behind so the pool can still close.
+			let reap = closed && frame_system::Pallet::<T>::can_dec_provider(&pool_account);
+			let native_amount = if reap {
+				T::Currency::free_balance(&pool_account)
+			} else if closed {
+				T::Currency::free_balance(&pool_account)
+					.saturating_sub(T::Currency::minimum_balance())
+			} else {
+				native_amount
+			};
+			T::Currency::transfer(
+				&pool_account,
+				&who,
+				native_amount,
+				if reap {
+					ExistenceRequirement::AllowDeath
+				} else {
+					ExistenceRequirement::KeepAlive
+				},
+			)?;
+
+			if closed {
+				<Pools<T>>::remove(asset_id);
+				<PriceAccumulators<T>>::remove(asset_id);
+			} else {
+				<Pools<T>>::insert(asset_id, pool);
+			}
+			if held == shares {
+				<Shares<T>>::remove(asset_id, &who);
+			} else {
+				<Shares<T>>::insert(asset_id, &who, held - shares);
+			}
+
+			Self::deposit_event(Event::LiquidityRemoved(
+				who,
+				asset_id,
+				native_amount,
+				asset_amount,
+				shares,
+			));
+			if closed {
+				Self::deposit_event(Event::PoolClosed(asset_id));
+			}
+			Ok(())
+		}
+
+		/// Swap exactly `native_in` of the native currency for at least `min_asset_out` of the
+		/// asset.
+		#[pallet::weight(T::WeightInfo::swap_native_for_asset())]
+		#[transactional]
+		pub fn swap_native_for_asset(
+			origin: OriginFor<T>,
+			asset_id: AssetIdOf<T>,
+			native_in: BalanceOf<T>,
+			min_asset_out: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!native_in.is_zero(), Error::<T>::ZeroAmount);
+
+			let mut pool = Self::pools(asset_id).ok_or(Error::<T>::PoolNotFound)?;
+			Self::update_price_accumulator(asset_id, &pool);
+			let asset_out =
+				Self::get_amount_out(native_in, pool.native_reserve, pool.asset_reserve)?;
+			ensure!(!asset_out.is_zero(), Error::<T>::ZeroAmount);
+			ensure!(asset_out >= min_asset_out, Error::<T>::SlippageExceeded);
+
+			let pool_account = Self::pool_account(asset_id);
+			T::Currency::transfer(&who, &pool_account, native_in, ExistenceRequirement::KeepAlive)?;
+			<T::Assets as fungibles::Transfer<_>>::transfer(
+				asset_id,
+				&pool_account,
+				&who,
+				asset_out,
+				true,
+			)?;
+
+			pool.native_reserve =
+				pool.native_reserve.checked_add(&native_in).ok_or(Error::<T>::Overflow)?;
+			pool.asset_reserve = pool.asset_reserve.saturating_sub(asset_out);
+			<Pools<T>>::insert(asset_id, pool);
+
+			Self::deposit_event(Event::SwappedNativeForAsset(who, asset_id, native_in, asset_out));
+			Ok(())
+		}
+
+		/// Swap exactly `asset_in` of the asset for at least `min_native_out` of the native
+		/// currency.
+		#[pallet::weight(T::WeightInfo::swap_asset_for_native())]
+		#[transactional]
+		pub fn swap_asset_for_native(
+			origin: OriginFor<T>,
+			asset_id: AssetIdOf<T>,
+			asset_in: BalanceOf<T>,
+			min_native_out: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!asset_in.is_zero(), Error::<T>::ZeroAmount);
+
+			let mut pool = Self::pools(asset_id).ok_or(Error::<T>::PoolNotFound)?;
+			Self::update_price_accumulator(asset_id, &pool);
+			let native_out =
+				Self::get_amount_out(asset_in, pool.asset_reserve, pool.native_reserve)?;
+			ensure!(!native_out.is_zero(), Error::<T>::ZeroAmount);
+			ensure!(native_out >= min_native_out, Error::<T>::SlippageExceeded);
+
+			let pool_account = Self::pool_account(asset_id);
+			<T::Assets as fungibles::Transfer<_>>::transfer(
+				asset_id,
+				&who,
+				&pool_account,
+				asset_in,
+				true,
+			)?;
+			T::Currency::transfer(
+				&pool_account,
+				&who,
+				native_out,
+				ExistenceRequirement::KeepAlive,
+			)?;
+
+			pool.asset_reserve =
+				pool.asset_reserve.checked_add(&asset_in).ok_or(Error::<T>::Overflow)?;
+			pool.native_reserve = pool.native_reserve.saturating_sub(native_out);
+			<Pools<T>>::insert(asset_id, pool);
+
+			Self::deposit_event(Event::SwappedAssetForNative(who, asset_id, asset_in, native_out));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account holding the reserves of the pool for `asset_id`.
+		pub fn pool_account(asset_id: AssetIdOf<T>) -> T::AccountId {
+			T::PalletId::get().into_sub_account(asset_id)
+		}
+
+		/// The amount of `asset_id` that swapping `native_in` would currently return, if the
+		/// asset has a pool.
+		///
+		/// This is a spot quote for user-facing estimates. It can be moved and restored within a
+		/// single block, e.g. inside `Utility::batch_all`, so fee or payout logic must use
+		/// `current_price_accumulator` instead.
+		pub fn quote_native_for_asset(
+			asset_id: AssetIdOf<T>,
+			native_in: BalanceOf<T>,
+		) -> Option<BalanceOf<T>> {
+			let pool = Self::pools(asset_id)?;
+			Self::get_amount_out(native_in, pool.native_reserve, pool.asset_reserve).ok()
+		}
+
+		/// The amount of native currency that swapping `asset_in` of `asset_id` would currently
+		/// return, if the asset has a pool.
+		///
+		/// Like `quote_native_for_asset`, this is a spot quote and must not be used as an oracle.
+		pub fn quote_asset_for_native(
+			asset_id: AssetIdOf<T>,
+			asset_in: BalanceOf<T>,
+		) -> Option<BalanceOf<T>> {
+			let pool = Self::pools(asset_id)?;
+			Self::get_amount_out(asset_in, pool.asset_reserve, pool.native_reserve).ok()
+		}
+
+		/// The cumulative prices of the pool for `asset_id` brought up to the current block, if the
+		/// asset has a pool.
+		///
+		/// Pallets needing a price take two of these observations some blocks apart and average
+		/// over them, as described on `PriceAccumulator`.
+		pub fn current_price_accumulator(
+			asset_id: AssetIdOf<T>,
+		) -> Option<PriceAccumulator<T::BlockNumber>> {
+			let pool = Self::pools(asset_id)?;
+			let accumulator = Self::price_accumulators(asset_id)?;
+			Some(Self::accumulate(&pool, accumulator, frame_system::Pallet::<T>::block_number()))
+		}
+
+		/// Bring the cumulative prices of `asset_id` up to the current block, using the reserves
+		/// of `pool` from before they are changed.
+		fn update_price_accumulator(asset_id: AssetIdOf<T>, pool: &Pool<BalanceOf<T>>) {
+			let now = frame_system::Pallet::<T>::block_number();
+			let accumulator = Self::price_accumulators(asset_id)
+				.unwrap_or(PriceAccumulator { last_update: now, ..Default::default() });
+			<PriceAccumulators<T>>::insert(asset_id, Self::accumulate(pool, accumulator, now));
+		}
+
+		fn accumulate(
+			pool: &Pool<BalanceOf<T>>,
+			mut accumulator: PriceAccumulator<T::BlockNumber>,
+			now: T::BlockNumber,
+		) -> PriceAccumulator<T::BlockNumber> {
+			let elapsed = now.saturating_sub(accumulator.last_update).saturated_into::<u128>();
+			let native_reserve = pool.native_reserve.saturated_into::<u128>();
+			let asset_reserve = pool.asset_reserve.saturated_into::<u128>();
+			// Each price is accumulated on its own: a pool ratio too lopsided to represent one
+			// price must not drop the other one from the interval.
+			let add = |sum: FixedU128, price: Option<FixedU128>| match price {
+				Some(price) => FixedU128::from_inner(
+					sum.into_inner().wrapping_add(price.into_inner().wrapping_mul(elapsed)),
+				),
+				None => sum,
+			};
+			accumulator.asset_price_cumulative = add(
+				accumulator.asset_price_cumulative,
+				FixedU128::checked_from_rational(native_reserve, asset_reserve),
+			);
+			accumulator.native_price_cumulative = add(
+				accumulator.native_price_cumulative,
+				FixedU128::checked_from_rational(asset_reserve, native_reserve),
+			);
+			accumulator.last_update = now;
+			accumulator
+		}
+
+		/// Output of a swap of `amount_in` against the given reserves, keeping
+		/// `reserve_in * reserve_out` constant after the swap fee is taken from the input.
+		pub fn get_amount_out(
+			amount_in: BalanceOf<T>,
+			reserve_in: BalanceOf<T>,
+			reserve_out: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, Error<T>> {
+			ensure!(
+				!reserve_in.is_zero() && !reserve_out.is_zero(),
+				Error::<T>::InsufficientLiquidity
+			);
+			let amount_in_after_fee =
+				amount_in.saturating_sub(T::SwapFee::get().mul_ceil(amount_in));
+			let reserve_in_after =
+				reserve_in.checked_add(&amount_in_after_fee).ok_or(Error::<T>::Overflow)?;
+			Self::mul_div(amount_in_after_fee, reserve_out, reserve_in_after)
+		}
+
+		/// `a * b / c` rounded down, without intermediate overflow.
+		fn mul_div(
+			a: BalanceOf<T>,
+			b: BalanceOf<T>,
+			c: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, Error<T>> {
+			Self::mul_div_rounding(a, b, c, false)
+		}
+
+		/// `a * b / c` rounded up, without intermediate overflow.
+		fn mul_div_ceil(
+			a: BalanceOf<T>,
+			b: BalanceOf<T>,
+			c: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, Error<T>> {
+			Self::mul_div_rounding(a, b, c, true)
+		}
+
+		fn mul_div_rounding(
+			a: BalanceOf<T>,
+			b: BalanceOf<T>,
+			c: BalanceOf<T>,
+			round_up: bool,
+		) -> Result<BalanceOf<T>, Error<T>> {
+			let to_u256 = |x: BalanceOf<T>| -> Result<U256, Error<T>> {
+				let x: u128 = x.try_into().map_err(|_| Error::<T>::Overflow)?;
+				Ok(U256::from(x))
+			};
+			let c = to_u256(c)?;
+			ensure!(!c.is_zero(), Error::<T>::Overflow);
+			let (mut quotient, remainder) = (to_u256(a)? * to_u256(b)?).div_mod(c);
+			if round_up && !remainder.is_zero() {
+				quotient += U256::one();
+			}
+			ensure!(quotient <= U256::from(u128::MAX), Error::<T>::Overflow);
+			quotient.low_u128().try_into().map_err(|_| Error::<T>::Overflow)
+		}
+	}
+}
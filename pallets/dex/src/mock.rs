@@ -0,0 +1,132 @@
+use crate as pallet_dex;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU16, ConstU32, ConstU64},
+	PalletId,
+};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	Permill,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+pub const ASSET: u32 = 0;
+pub const NON_SUFFICIENT_ASSET: u32 = 1;
+pub const EXISTENTIAL_DEPOSIT: u64 = 10;
+pub const MIN_ASSET_BALANCE: u64 = 10;
+pub const INITIAL_BALANCE: u64 = 1_000_000;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
+		Dex: pallet_dex::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<EXISTENTIAL_DEPOSIT>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl pallet_assets::Config for Test {
+	type Event = Event;
+	type Balance = u64;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type AssetDeposit = ConstU64<0>;
+	type MetadataDepositBase = ConstU64<0>;
+	type MetadataDepositPerByte = ConstU64<0>;
+	type ApprovalDeposit = ConstU64<0>;
+	type StringLimit = ConstU32<50>;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const DexPalletId: PalletId = PalletId(*b"py/dexpl");
+	pub const SwapFee: Permill = Permill::from_perthousand(3);
+}
+
+impl pallet_dex::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type Assets = Assets;
+	type PalletId = DexPalletId;
+	type SwapFee = SwapFee;
+	type WeightInfo = ();
+}
+
+// Build genesis storage according to the mock runtime, and give ALICE and BOB the native currency
+// and both assets. `ASSET` is sufficient, while `NON_SUFFICIENT_ASSET` behaves like assets created
+// with `Assets::create` and needs its holders to exist through their native balance.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, INITIAL_BALANCE), (BOB, INITIAL_BALANCE)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Assets::force_create(Origin::root(), ASSET, ALICE, true, 1).unwrap();
+		Assets::mint(Origin::signed(ALICE), ASSET, ALICE, INITIAL_BALANCE).unwrap();
+		Assets::mint(Origin::signed(ALICE), ASSET, BOB, INITIAL_BALANCE).unwrap();
+		Assets::force_create(Origin::root(), NON_SUFFICIENT_ASSET, ALICE, false, MIN_ASSET_BALANCE)
+			.unwrap();
+		Assets::mint(Origin::signed(ALICE), NON_SUFFICIENT_ASSET, ALICE, INITIAL_BALANCE).unwrap();
+		Assets::mint(Origin::signed(ALICE), NON_SUFFICIENT_ASSET, BOB, INITIAL_BALANCE).unwrap();
+	});
+	ext
+}
@@ -0,0 +1,384 @@
+use crate::{mock::*, Error, Event as DexEvent, Pool, PriceAccumulator};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{FixedPointNumber, FixedU128, TokenError};
+
+fn seed_pool() {
+	assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), ASSET, 10_000, 40_000, 0));
+}
+
+#[test]
+fn add_liquidity_creates_pool() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+
+		assert_eq!(
+			Dex::pools(ASSET),
+			Some(Pool { native_reserve: 10_000, asset_reserve: 40_000, total_shares: 10_000 })
+		);
+		assert_eq!(Dex::shares(ASSET, ALICE), 10_000);
+		let pool_account = Dex::pool_account(ASSET);
+		assert_eq!(Balances::free_balance(pool_account), 10_000);
+		assert_eq!(Assets::balance(ASSET, pool_account), 40_000);
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE - 10_000);
+		assert_eq!(Assets::balance(ASSET, ALICE), INITIAL_BALANCE - 40_000);
+		System::assert_last_event(Event::Dex(DexEvent::LiquidityAdded(
+			ALICE, ASSET, 10_000, 40_000, 10_000,
+		)));
+	});
+}
+
+#[test]
+fn add_liquidity_follows_pool_ratio() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+
+		assert_ok!(Dex::add_liquidity(Origin::signed(BOB), ASSET, 5_000, 30_000, 5_000));
+		assert_eq!(Dex::shares(ASSET, BOB), 5_000);
+		assert_eq!(Assets::balance(ASSET, BOB), INITIAL_BALANCE - 20_000);
+		assert_eq!(
+			Dex::pools(ASSET),
+			Some(Pool { native_reserve: 15_000, asset_reserve: 60_000, total_shares: 15_000 })
+		);
+	});
+}
+
+#[test]
+fn add_liquidity_rounds_asset_deposit_up() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), ASSET, 10_000, 40_001, 0));
+
+		// 5_000 * 40_001 / 10_000 = 20_000.5, charged in favour of the pool.
+		assert_ok!(Dex::add_liquidity(Origin::signed(BOB), ASSET, 5_000, 30_000, 5_000));
+		assert_eq!(Assets::balance(ASSET, BOB), INITIAL_BALANCE - 20_001);
+		assert_eq!(
+			Dex::pools(ASSET),
+			Some(Pool { native_reserve: 15_000, asset_reserve: 60_002, total_shares: 15_000 })
+		);
+	});
+}
+
+#[test]
+fn add_liquidity_respects_limits() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), ASSET, 0, 40_000, 0),
+			Error::<Test>::ZeroAmount
+		);
+		seed_pool();
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(BOB), ASSET, 5_000, 19_999, 0),
+			Error::<Test>::SlippageExceeded
+		);
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(BOB), ASSET, 5_000, 30_000, 5_001),
+			Error::<Test>::SlippageExceeded
+		);
+	});
+}
+
+#[test]
+fn swap_native_for_asset_works() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+		assert_eq!(Dex::quote_native_for_asset(ASSET, 1_000), Some(3_626));
+
+		assert_ok!(Dex::swap_native_for_asset(Origin::signed(BOB), ASSET, 1_000, 3_626));
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE - 1_000);
+		assert_eq!(Assets::balance(ASSET, BOB), INITIAL_BALANCE + 3_626);
+		assert_eq!(
+			Dex::pools(ASSET),
+			Some(Pool { native_reserve: 11_000, asset_reserve: 36_374, total_shares: 10_000 })
+		);
+		System::assert_last_event(Event::Dex(DexEvent::SwappedNativeForAsset(
+			BOB, ASSET, 1_000, 3_626,
+		)));
+	});
+}
+
+#[test]
+fn swap_asset_for_native_works() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+		assert_eq!(Dex::quote_asset_for_native(ASSET, 4_000), Some(906));
+
+		assert_ok!(Dex::swap_asset_for_native(Origin::signed(BOB), ASSET, 4_000, 900));
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE + 906);
+		assert_eq!(Assets::balance(ASSET, BOB), INITIAL_BALANCE - 4_000);
+		assert_eq!(
+			Dex::pools(ASSET),
+			Some(Pool { native_reserve: 9_094, asset_reserve: 44_000, total_shares: 10_000 })
+		);
+	});
+}
+
+#[test]
+fn swap_fails_without_pool_or_above_slippage() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Dex::swap_native_for_asset(Origin::signed(BOB), ASSET, 1_000, 0),
+			Error::<Test>::PoolNotFound
+		);
+		assert_eq!(Dex::quote_native_for_asset(ASSET, 1_000), None);
+
+		seed_pool();
+		assert_noop!(
+			Dex::swap_native_for_asset(Origin::signed(BOB), ASSET, 1_000, 3_627),
+			Error::<Test>::SlippageExceeded
+		);
+		assert_noop!(
+			Dex::swap_asset_for_native(Origin::signed(BOB), ASSET, 4_000, 907),
+			Error::<Test>::SlippageExceeded
+		);
+	});
+}
+
+#[test]
+fn remove_liquidity_pays_out_share_of_reserves() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), ASSET, 2_500, 2_500, 10_000));
+		assert_eq!(Dex::shares(ASSET, ALICE), 7_500);
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE - 7_500);
+		assert_eq!(Assets::balance(ASSET, ALICE), INITIAL_BALANCE - 30_000);
+		assert_eq!(
+			Dex::pools(ASSET),
+			Some(Pool { native_reserve: 7_500, asset_reserve: 30_000, total_shares: 7_500 })
+		);
+	});
+}
+
+#[test]
+fn removing_last_shares_closes_pool() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), ASSET, 10_000, 0, 0));
+		assert_eq!(Dex::pools(ASSET), None);
+		assert_eq!(Dex::shares(ASSET, ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE);
+		assert_eq!(Assets::balance(ASSET, ALICE), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(Dex::pool_account(ASSET)), 0);
+		System::assert_last_event(Event::Dex(DexEvent::PoolClosed(ASSET)));
+	});
+}
+
+#[test]
+fn remove_liquidity_requires_shares() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(BOB), ASSET, 1, 0, 0),
+			Error::<Test>::InsufficientShares
+		);
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(ALICE), ASSET, 2_500, 2_501, 0),
+			Error::<Test>::SlippageExceeded
+		);
+	});
+}
+
+#[test]
+fn new_pool_needs_existential_deposit_and_min_balance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Dex::add_liquidity(
+				Origin::signed(ALICE),
+				NON_SUFFICIENT_ASSET,
+				EXISTENTIAL_DEPOSIT - 1,
+				40_000,
+				0
+			),
+			pallet_balances::Error::<Test>::ExistentialDeposit
+		);
+		// The pool account's asset balance would start below the asset's minimum balance.
+		assert_noop!(
+			Dex::add_liquidity(
+				Origin::signed(ALICE),
+				NON_SUFFICIENT_ASSET,
+				10_000,
+				MIN_ASSET_BALANCE - 1,
+				0
+			),
+			TokenError::BelowMinimum
+		);
+
+		assert_ok!(Dex::add_liquidity(
+			Origin::signed(ALICE),
+			NON_SUFFICIENT_ASSET,
+			EXISTENTIAL_DEPOSIT,
+			MIN_ASSET_BALANCE,
+			0
+		));
+	});
+}
+
+#[test]
+fn remove_liquidity_keeps_open_pool_alive() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::add_liquidity(
+			Origin::signed(ALICE),
+			NON_SUFFICIENT_ASSET,
+			10_000,
+			40_000,
+			0
+		));
+		assert_ok!(Dex::add_liquidity(Origin::signed(BOB), NON_SUFFICIENT_ASSET, 5, 20, 0));
+
+		// Only BOB's 5 native units would remain, which is below the existential deposit.
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(ALICE), NON_SUFFICIENT_ASSET, 10_000, 0, 0),
+			pallet_balances::Error::<Test>::KeepAlive
+		);
+	});
+}
+
+#[test]
+fn closing_pool_sweeps_assets_sent_to_pool_account() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::add_liquidity(
+			Origin::signed(ALICE),
+			NON_SUFFICIENT_ASSET,
+			10_000,
+			40_000,
+			0
+		));
+		let pool_account = Dex::pool_account(NON_SUFFICIENT_ASSET);
+		assert_ok!(Assets::transfer(
+			Origin::signed(BOB),
+			NON_SUFFICIENT_ASSET,
+			pool_account,
+			MIN_ASSET_BALANCE
+		));
+
+		assert_ok!(Dex::remove_liquidity(
+			Origin::signed(ALICE),
+			NON_SUFFICIENT_ASSET,
+			10_000,
+			0,
+			0
+		));
+		assert_eq!(Dex::pools(NON_SUFFICIENT_ASSET), None);
+		assert_eq!(
+			Assets::balance(NON_SUFFICIENT_ASSET, ALICE),
+			INITIAL_BALANCE + MIN_ASSET_BALANCE
+		);
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE);
+		assert!(!System::account_exists(&pool_account));
+		System::assert_has_event(Event::Dex(DexEvent::LiquidityRemoved(
+			ALICE,
+			NON_SUFFICIENT_ASSET,
+			10_000,
+			40_000 + MIN_ASSET_BALANCE,
+			10_000,
+		)));
+	});
+}
+
+#[test]
+fn closing_pool_leaves_existential_deposit_when_other_assets_are_held() {
+	new_test_ext().execute_with(|| {
+		const OTHER_ASSET: u32 = 2;
+		assert_ok!(Assets::force_create(
+			Origin::root(),
+			OTHER_ASSET,
+			BOB,
+			false,
+			MIN_ASSET_BALANCE
+		));
+		assert_ok!(Assets::mint(Origin::signed(BOB), OTHER_ASSET, BOB, MIN_ASSET_BALANCE));
+		assert_ok!(Dex::add_liquidity(
+			Origin::signed(ALICE),
+			NON_SUFFICIENT_ASSET,
+			10_000,
+			40_000,
+			0
+		));
+		// Holding another non-sufficient asset stops the pool account from being reaped.
+		let pool_account = Dex::pool_account(NON_SUFFICIENT_ASSET);
+		assert_ok!(Assets::transfer(
+			Origin::signed(BOB),
+			OTHER_ASSET,
+			pool_account,
+			MIN_ASSET_BALANCE
+		));
+
+		assert_ok!(Dex::remove_liquidity(
+			Origin::signed(ALICE),
+			NON_SUFFICIENT_ASSET,
+			10_000,
+			0,
+			0
+		));
+		assert_eq!(Dex::pools(NON_SUFFICIENT_ASSET), None);
+		assert_eq!(Assets::balance(NON_SUFFICIENT_ASSET, ALICE), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE - EXISTENTIAL_DEPOSIT);
+		assert_eq!(Balances::free_balance(pool_account), EXISTENTIAL_DEPOSIT);
+		System::assert_has_event(Event::Dex(DexEvent::LiquidityRemoved(
+			ALICE,
+			NON_SUFFICIENT_ASSET,
+			10_000 - EXISTENTIAL_DEPOSIT,
+			40_000,
+			10_000,
+		)));
+	});
+}
+
+#[test]
+fn price_accumulator_sums_prices_over_blocks() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+		assert_eq!(
+			Dex::price_accumulators(ASSET),
+			Some(PriceAccumulator { last_update: 1, ..Default::default() })
+		);
+
+		// The 10 blocks since creation are accumulated at the price before the swap.
+		System::set_block_number(11);
+		assert_ok!(Dex::swap_native_for_asset(Origin::signed(BOB), ASSET, 1_000, 0));
+		assert_eq!(
+			Dex::price_accumulators(ASSET),
+			Some(PriceAccumulator {
+				asset_price_cumulative: FixedU128::saturating_from_rational(25, 10),
+				native_price_cumulative: FixedU128::saturating_from_integer(40),
+				last_update: 11,
+			})
+		);
+	});
+}
+
+#[test]
+fn price_accumulator_ignores_swaps_within_a_block() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+
+		// Moving the price and restoring it within one block leaves no trace in the sums.
+		assert_ok!(Dex::swap_native_for_asset(Origin::signed(BOB), ASSET, 10_000, 0));
+		assert_ok!(Dex::swap_asset_for_native(Origin::signed(BOB), ASSET, 19_000, 0));
+		assert_eq!(
+			Dex::price_accumulators(ASSET),
+			Some(PriceAccumulator { last_update: 1, ..Default::default() })
+		);
+
+		let pool = Dex::pools(ASSET).unwrap();
+		let asset_price =
+			FixedU128::checked_from_rational(pool.native_reserve, pool.asset_reserve).unwrap();
+		System::set_block_number(5);
+		assert_eq!(
+			Dex::current_price_accumulator(ASSET).map(|a| a.asset_price_cumulative),
+			Some(FixedU128::from_inner(asset_price.into_inner() * 4))
+		);
+	});
+}
+
+#[test]
+fn closing_pool_removes_price_accumulator() {
+	new_test_ext().execute_with(|| {
+		seed_pool();
+
+		assert_ok!(Dex::remove_liquidity(Origin::signed(ALICE), ASSET, 10_000, 0, 0));
+		assert_eq!(Dex::price_accumulators(ASSET), None);
+		assert_eq!(Dex::current_price_accumulator(ASSET), None);
+	});
+}
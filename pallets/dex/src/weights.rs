@@ -0,0 +1,97 @@
+//! Weights for pallet_dex.
+//!
+//! These weights are hand-written estimates, not benchmark output. The storage access counts are
+//! the worst case of each call, counted from the code. The base execution times are conservative
+//! guesses. Replace this file with the output of the benchmarks in `benchmarking.rs`, run on
+//! reference hardware with:
+//!
+//! ./target/release/node-template benchmark --chain dev --execution wasm --wasm-execution compiled
+//! --pallet pallet_dex --extrinsic '*' --steps 50 --repeat 20 --output pallets/dex/src/weights.rs
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use core::marker::PhantomData;
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+
+/// Weight functions needed for pallet_dex.
+pub trait WeightInfo {
+	fn add_liquidity() -> Weight;
+	fn remove_liquidity() -> Weight;
+	fn swap_native_for_asset() -> Weight;
+	fn swap_asset_for_native() -> Weight;
+}
+
+/// Estimated weights for pallet_dex, until it is benchmarked.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: Dex Pools (r:1 w:1)
+	// Storage: Dex PriceAccumulators (r:1 w:1)
+	// Storage: Dex Shares (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn add_liquidity() -> Weight {
+		(100_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(8 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:1)
+	// Storage: Dex PriceAccumulators (r:1 w:1)
+	// Storage: Dex Shares (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn remove_liquidity() -> Weight {
+		(100_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(8 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:1)
+	// Storage: Dex PriceAccumulators (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn swap_native_for_asset() -> Weight {
+		(90_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+	// Storage: Dex Pools (r:1 w:1)
+	// Storage: Dex PriceAccumulators (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn swap_asset_for_native() -> Weight {
+		(90_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn add_liquidity() -> Weight {
+		(100_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(8 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(8 as Weight))
+	}
+	fn remove_liquidity() -> Weight {
+		(100_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(8 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(8 as Weight))
+	}
+	fn swap_native_for_asset() -> Weight {
+		(90_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(7 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(7 as Weight))
+	}
+	fn swap_asset_for_native() -> Weight {
+		(90_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(7 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(7 as Weight))
+	}
+}
@@ -0,0 +1,51 @@
+use crate::*;
+use frame_support::assert_ok;
+
+const INITIAL_BALANCE: Balance = 1 << 60;
+
+fn alice() -> AccountId {
+	AccountId::from([1u8; 32])
+}
+
+fn bob() -> AccountId {
+	AccountId::from([2u8; 32])
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	pallet_balances::GenesisConfig::<Runtime> { balances: vec![(alice(), INITIAL_BALANCE)] }
+		.assimilate_storage(&mut t)
+		.unwrap();
+	t.into()
+}
+
+#[test]
+fn batch_all_dispatches_every_call() {
+	new_test_ext().execute_with(|| {
+		let calls = vec![
+			Call::Balances(BalancesCall::transfer { dest: Address::Id(bob()), value: 1_000 }),
+			Call::TemplateModule(pallet_template::Call::do_something { something: 42 }),
+		];
+		assert_ok!(Utility::batch_all(Origin::signed(alice()), calls));
+
+		assert_eq!(Balances::free_balance(bob()), 1_000);
+		assert_eq!(TemplateModule::something(), Some(42));
+	});
+}
+
+#[test]
+fn batch_all_reverts_every_call_on_failure() {
+	new_test_ext().execute_with(|| {
+		// `cause_error` overflows the value stored by `do_something`, failing the whole batch.
+		let calls = vec![
+			Call::Balances(BalancesCall::transfer { dest: Address::Id(bob()), value: 1_000 }),
+			Call::TemplateModule(pallet_template::Call::do_something { something: u32::MAX }),
+			Call::TemplateModule(pallet_template::Call::cause_error {}),
+		];
+		assert!(Utility::batch_all(Origin::signed(alice()), calls).is_err());
+
+		assert_eq!(Balances::free_balance(alice()), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(bob()), 0);
+		assert_eq!(TemplateModule::something(), None);
+	});
+}